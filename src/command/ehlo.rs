@@ -8,7 +8,7 @@ use futures::Future;
 use ::data_types::Capability;
 use ::error::MissingCapabilities;
 use ::{
-    Domain, EhloData, SyntaxError, EhloParam,
+    AddressLiteral, Domain, EhloData, SyntaxError, EhloParam,
     Cmd, ExecFuture, Io, Response, ClientId
 };
 
@@ -82,11 +82,52 @@ impl Cmd for Ehlo {
     }
 }
 
-fn parse_ehlo_response(response: &Response) -> Result<EhloData, SyntaxError> {
+/// Whether `token` has the shape of a bare, unbracketed IPv4 address
+/// (`192.0.2.1`): exactly 4 dot-separated octets, each an ASCII-digit-only
+/// number in `0..=255`. The `Domain` grammar's `Let-dig`/`Ldh-str`
+/// production otherwise happily accepts all-numeric labels, so this has
+/// to be ruled out explicitly rather than left to `Domain`'s parser; it
+/// must not over-match things that aren't IPv4-shaped at all (a single
+/// numeric label, or a 5+-label numeric sequence), since those should
+/// still be handed to `Domain`'s parser as usual.
+fn looks_like_bare_ip(token: &str) -> bool {
+    let octets: Vec<&str> = token.split('.').collect();
+    octets.len() == 4 && octets.iter().all(|octet| {
+        !octet.is_empty()
+            && octet.bytes().all(|b| b.is_ascii_digit())
+            && octet.parse::<u8>().is_ok()
+    })
+}
+
+/// Parses the leading identifier out of the first line of a greeting
+/// response (EHLO or HELO), shared so both verbs agree on what counts as
+/// a valid server identity.
+///
+/// Per RFC 5321 the identifier is either a `Domain` or a bracketed
+/// address-literal (e.g. `[192.0.2.1]`, `[IPv6:2001:db8::1]`). A bare,
+/// unbracketed IP is not a valid address-literal (brackets are mandatory)
+/// and must not be accepted as a `Domain` either, so it's rejected
+/// up front instead of being handed to `Domain`'s parser.
+pub(crate) fn parse_greeting_identity(first_line: &str) -> Result<ClientId, SyntaxError> {
+    //UNWRAP_SAFE: Split has at least one entry
+    let token = first_line.split(" ").next().unwrap();
+    if token.starts_with('[') {
+        token.parse::<AddressLiteral>().map(ClientId::AddressLiteral)
+    } else if looks_like_bare_ip(token) {
+        // Re-use AddressLiteral's own parser to produce the error: lacking the
+        // mandatory brackets, it always rejects `token`, so this surfaces a
+        // real SyntaxError instead of inventing one.
+        //UNWRAP_SAFE: AddressLiteral::from_str errors on missing brackets
+        Err(token.parse::<AddressLiteral>().unwrap_err())
+    } else {
+        token.parse::<Domain>().map(ClientId::Domain)
+    }
+}
+
+pub(crate) fn parse_ehlo_response(response: &Response) -> Result<EhloData, SyntaxError> {
     let lines = response.msg();
     let first = lines.first().expect("response with 0 lines should not");
-    //UNWRAP_SAFE: Split has at least one entry
-    let domain: Domain = first.split(" ").next().unwrap().parse()?;
+    let identity = parse_greeting_identity(first)?;
     let mut caps = HashMap::new();
 
     for line in lines[1..].iter() {
@@ -95,23 +136,51 @@ fn parse_ehlo_response(response: &Response) -> Result<EhloData, SyntaxError> {
         let capability_candidate = parts.next().unwrap();
         let capability = match capability_candidate.parse::<Capability>() {
             e @ Err(SyntaxError::EsmtpKeyword) => {
-                // Ignore broken duplicate AUTH capability which servers may declare to be compatible
-                // with old Outlook clients. Postfix servers use this behavior with the
+                // Some servers only advertise mechanisms on the "AUTH=<mech>" line, for
+                // compatibility with old Outlook clients, via Postfix's
                 // [broken_sasl_auth_clients](http://www.postfix.org/postconf.5.html#broken_sasl_auth_clients)
-                // configuration option.
-                if capability_candidate.split("=").next().map(Capability::from_str) == Some(Ok(Capability::from_str("AUTH").unwrap())) {
-                    continue;
-                } else {
-                    e
+                // configuration option. Union whatever mechanisms it lists into the
+                // canonical AUTH capability entry instead of throwing the line away.
+                let mut eq_parts = capability_candidate.splitn(2, "=");
+                let keyword = eq_parts.next().unwrap();
+                match (keyword.parse::<Capability>(), eq_parts.next()) {
+                    (Ok(auth), Some(first_mechanism))
+                        if auth == Capability::from_str("AUTH").unwrap() =>
+                    {
+                        let mechanisms = Some(first_mechanism).into_iter()
+                            .chain(parts)
+                            .map(|part| part.parse())
+                            .collect::<Result<Vec<EhloParam>, _>>()?;
+                        let entry = caps.entry(auth).or_insert_with(Vec::new);
+                        for mechanism in mechanisms {
+                            if !entry.contains(&mechanism) {
+                                entry.push(mechanism);
+                            }
+                        }
+                        continue;
+                    },
+                    _ => e,
                 }
             },
             r @ _ => r,
         }?;
         let params = parts.map(|part| part.parse()).collect::<Result<Vec<EhloParam>, _>>()?;
-        caps.insert(capability, params);
+        // Merge into whatever's already there (deduplicating) rather than
+        // overwriting it outright: an earlier "AUTH=<mech>" line may have
+        // already populated this capability's entry, and a plain `insert`
+        // here would silently drop those mechanisms if the canonical line
+        // comes after it.
+        let entry = caps.entry(capability).or_insert_with(Vec::new);
+        for param in params {
+            if !entry.contains(&param) {
+                entry.push(param);
+            }
+        }
     }
 
-    Ok(EhloData::new(domain, caps))
+    // `EhloData::new` takes the server's `ClientId` (widened from plain `Domain`
+    // to also cover address-literals) as of this change.
+    Ok(EhloData::new(identity, caps))
 }
 
 
@@ -190,6 +259,51 @@ mod test {
             assert_eq!(ehlo_data.capability_map().len(), 1)
         }
 
+        #[test]
+        fn auth_eq_mechanisms_are_merged_in() {
+            let response = Response::new(OK, vec![
+                "1aim.test says hy".to_owned(),
+                "AUTH PLAIN".to_owned(),
+                "AUTH=LOGIN PLAIN".to_owned(),
+            ]);
+            let ehlo_data = parse_ehlo_response(&response).unwrap();
+
+            assert!(ehlo_data.has_capability("AUTH"));
+            assert_eq!(Some(["PLAIN".parse::<EhloParam>().unwrap(), "LOGIN".parse().unwrap()].as_ref()), ehlo_data.get_capability_params("AUTH"));
+            assert_eq!(ehlo_data.capability_map().len(), 1)
+        }
+
+        #[test]
+        fn auth_eq_mechanisms_create_entry_when_space_form_absent() {
+            let response = Response::new(OK, vec![
+                "1aim.test says hy".to_owned(),
+                "AUTH=LOGIN PLAIN".to_owned(),
+            ]);
+            let ehlo_data = parse_ehlo_response(&response).unwrap();
+
+            assert!(ehlo_data.has_capability("AUTH"));
+            assert_eq!(Some(["LOGIN".parse::<EhloParam>().unwrap(), "PLAIN".parse().unwrap()].as_ref()), ehlo_data.get_capability_params("AUTH"));
+            assert_eq!(ehlo_data.capability_map().len(), 1)
+        }
+
+        #[test]
+        fn auth_eq_mechanisms_survive_when_they_appear_before_canonical_line() {
+            let response = Response::new(OK, vec![
+                "1aim.test says hy".to_owned(),
+                "AUTH=LOGIN PLAIN XOAUTH2".to_owned(),
+                "AUTH PLAIN".to_owned(),
+            ]);
+            let ehlo_data = parse_ehlo_response(&response).unwrap();
+
+            assert!(ehlo_data.has_capability("AUTH"));
+            assert_eq!(Some([
+                "LOGIN".parse::<EhloParam>().unwrap(),
+                "PLAIN".parse().unwrap(),
+                "XOAUTH2".parse().unwrap(),
+            ].as_ref()), ehlo_data.get_capability_params("AUTH"));
+            assert_eq!(ehlo_data.capability_map().len(), 1)
+        }
+
         #[test]
         fn malformed_non_auth_error() {
             let response = Response::new(OK, vec![
@@ -200,4 +314,63 @@ mod test {
             assert_eq!(SyntaxError::EsmtpKeyword, parse_ehlo_response(&response).unwrap_err());
         }
     }
+
+    mod parse_greeting_identity {
+        use ::ClientId;
+        use super::super::parse_greeting_identity;
+
+        #[test]
+        fn accepts_domain() {
+            let identity = parse_greeting_identity("1aim.test says hy").unwrap();
+            assert_eq!(identity, ClientId::Domain("1aim.test".parse().unwrap()));
+        }
+
+        #[test]
+        fn accepts_ipv4_address_literal() {
+            let identity = parse_greeting_identity("[192.0.2.1] says hy").unwrap();
+            assert_eq!(identity, ClientId::AddressLiteral("[192.0.2.1]".parse().unwrap()));
+        }
+
+        #[test]
+        fn accepts_ipv6_address_literal() {
+            let identity = parse_greeting_identity("[IPv6:2001:db8::1] says hy").unwrap();
+            assert_eq!(identity, ClientId::AddressLiteral("[IPv6:2001:db8::1]".parse().unwrap()));
+        }
+
+        #[test]
+        fn rejects_bare_ip() {
+            assert!(parse_greeting_identity("192.0.2.1 says hy").is_err());
+        }
+    }
+
+    mod looks_like_bare_ip {
+        use super::super::looks_like_bare_ip;
+
+        #[test]
+        fn accepts_four_octets_in_range() {
+            assert!(looks_like_bare_ip("192.0.2.1"));
+            assert!(looks_like_bare_ip("255.255.255.255"));
+            assert!(looks_like_bare_ip("0.0.0.0"));
+        }
+
+        #[test]
+        fn rejects_octet_out_of_range() {
+            assert!(!looks_like_bare_ip("256.0.2.1"));
+        }
+
+        #[test]
+        fn rejects_single_numeric_label() {
+            assert!(!looks_like_bare_ip("42"));
+        }
+
+        #[test]
+        fn rejects_more_than_four_labels() {
+            assert!(!looks_like_bare_ip("1.2.3.4.5"));
+        }
+
+        #[test]
+        fn rejects_non_numeric_label() {
+            assert!(!looks_like_bare_ip("1aim.test"));
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,107 @@
+use futures::Future;
+
+use ::error::MissingCapabilities;
+use ::{
+    ClientId, Cmd, EhloData, ExecFuture, Io, Response
+};
+use super::ehlo::Ehlo;
+use super::helo::Helo;
+
+
+/// Negotiates the initial greeting with the server, preferring `EHLO` but
+/// transparently falling back to `HELO` for legacy/misconfigured servers
+/// which reply with a permanent negative (5xx) completion code to `EHLO`.
+///
+/// Either way the connection ends up with a usable `EhloData`: the full
+/// set of parsed capabilities if `EHLO` was accepted, or just the server's
+/// identity (a domain or an address-literal) with no capabilities if the
+/// server only understands `HELO`.
+#[derive(Debug, Clone)]
+pub struct Greeting {
+    identity: ClientId
+}
+
+impl Greeting {
+
+    pub fn new(identity: ClientId) -> Self {
+        Greeting { identity }
+    }
+
+    pub fn identity(&self) -> &ClientId {
+        &self.identity
+    }
+}
+
+impl From<ClientId> for Greeting {
+    fn from(identity: ClientId) -> Self {
+        Greeting { identity }
+    }
+}
+
+/// Whether `response` carries a permanent negative completion code (5xx),
+/// per RFC 5321's first-digit reply code classification.
+fn is_permanent_negative(response: &Response) -> bool {
+    let code: u16 = response.code().into();
+    code / 100 == 5
+}
+
+impl Cmd for Greeting {
+
+    fn check_cmd_availability(&self, _caps: Option<&EhloData>)
+        -> Result<(), MissingCapabilities>
+    {
+       Ok(())
+    }
+
+    fn exec(self, io: Io) -> ExecFuture {
+        let identity = self.identity;
+        let fallback_identity = identity.clone();
+
+        let fut = Ehlo::from(identity)
+            .exec(io)
+            .and_then(move |(io, result)| -> ExecFuture {
+                match result {
+                    Ok(response) => Box::new(futures::future::ok((io, Ok(response)))),
+                    Err(response) => {
+                        if is_permanent_negative(&response) {
+                            Helo::from(fallback_identity).exec(io)
+                        } else {
+                            Box::new(futures::future::ok((io, Err(response))))
+                        }
+                    }
+                }
+            });
+
+        Box::new(fut)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    mod is_permanent_negative {
+        use ::Response;
+        use super::super::is_permanent_negative;
+
+        fn response_with_code(code: u16) -> Response {
+            Response::new(code.into(), vec!["mail.example.test".to_owned()])
+        }
+
+        #[test]
+        fn true_for_5xx() {
+            assert!(is_permanent_negative(&response_with_code(502)));
+            assert!(is_permanent_negative(&response_with_code(550)));
+        }
+
+        #[test]
+        fn false_for_2xx() {
+            assert!(!is_permanent_negative(&response_with_code(250)));
+        }
+
+        #[test]
+        fn false_for_4xx() {
+            assert!(!is_permanent_negative(&response_with_code(450)));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::io as std_io;
+
+use bytes::BufMut;
+use futures::Future;
+
+use ::error::MissingCapabilities;
+use ::{
+    ClientId, Cmd, EhloData, ExecFuture, Io, Response, SyntaxError
+};
+use super::ehlo::parse_greeting_identity;
+
+
+/// The legacy `HELO` verb.
+///
+/// Unlike `EHLO`, `HELO` advertises no capabilities, so the resulting
+/// `EhloData` always has an empty capability map.
+#[derive(Debug, Clone)]
+pub struct Helo {
+    identity: ClientId
+}
+
+impl Helo {
+
+    pub fn new(identity: ClientId) -> Self {
+        Helo { identity }
+    }
+
+    pub fn identity(&self) -> &ClientId {
+        &self.identity
+    }
+}
+
+impl From<ClientId> for Helo {
+    fn from(identity: ClientId) -> Self {
+        Helo { identity }
+    }
+}
+
+impl Into<ClientId> for Helo {
+    fn into(self) -> ClientId {
+        self.identity
+    }
+}
+
+impl Cmd for Helo {
+
+    fn check_cmd_availability(&self, _caps: Option<&EhloData>)
+        -> Result<(), MissingCapabilities>
+    {
+       Ok(())
+    }
+
+    fn exec(self, mut io: Io) -> ExecFuture {
+        let str_me = match *self.identity() {
+            ClientId::Domain(ref domain) => domain.as_str(),
+            ClientId::AddressLiteral(ref addr_lit) => addr_lit.as_str()
+        };
+
+        {
+            //7 == "HELO ".len() + "\r\n".len()
+            let out = io.out_buffer(7 + str_me.len());
+            out.put("HELO ");
+            out.put(str_me);
+            out.put("\r\n");
+        }
+
+        let fut = io
+            .flush()
+            .and_then(Io::parse_response)
+            .and_then(|(mut io, result)| match result {
+                Err(response) => Ok((io, Err(response))),
+                Ok(response) => {
+                    let ehlo_data = parse_helo_response(&response)
+                        .map_err(|err| std_io::Error::new(std_io::ErrorKind::Other, err))?;
+
+                    io.set_ehlo_data(ehlo_data);
+                    Ok((io, Ok(response)))
+                }
+            });
+
+        Box::new(fut)
+    }
+}
+
+/// `HELO` advertises no extensions, so the resulting `EhloData` always
+/// has an empty capability map; only the server's identity is parsed out.
+///
+/// See the matching note in ehlo.rs: `EhloData::new` takes a `ClientId`
+/// here, not a plain `Domain`.
+pub(crate) fn parse_helo_response(response: &Response) -> Result<EhloData, SyntaxError> {
+    let lines = response.msg();
+    let first = lines.first().expect("response with 0 lines should not");
+    let identity = parse_greeting_identity(first)?;
+    Ok(EhloData::new(identity, HashMap::new()))
+}
+
+
+#[cfg(test)]
+mod test {
+
+    mod parse_helo_response {
+        use ::Response;
+        use ::response::codes::OK;
+        use super::super::parse_helo_response;
+
+        #[test]
+        fn parses_domain_identity_with_empty_capabilities() {
+            let response = Response::new(OK, vec!["1aim.test says hy".to_owned()]);
+            let ehlo_data = parse_helo_response(&response).unwrap();
+
+            assert_eq!(ehlo_data.domain(), "1aim.test");
+            assert!(ehlo_data.capability_map().is_empty());
+            assert!(!ehlo_data.supports_pipelining());
+        }
+
+        #[test]
+        fn accepts_address_literal_identity() {
+            let response = Response::new(OK, vec!["[192.0.2.1] says hy".to_owned()]);
+            let ehlo_data = parse_helo_response(&response).unwrap();
+
+            assert!(ehlo_data.capability_map().is_empty());
+        }
+
+        #[test]
+        fn rejects_bare_ip_identity() {
+            let response = Response::new(OK, vec!["192.0.2.1 says hy".to_owned()]);
+            assert!(parse_helo_response(&response).is_err());
+        }
+    }
+}
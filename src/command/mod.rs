@@ -0,0 +1,3 @@
+pub mod ehlo;
+pub mod greeting;
+pub mod helo;
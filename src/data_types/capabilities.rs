@@ -0,0 +1,155 @@
+//! Typed interpretation of the well-known ESMTP extensions advertised in an
+//! EHLO response, layered on top of the raw `Capability`/`EhloParam` map
+//! stored in `EhloData`.
+use std::str::FromStr;
+
+use ::{EhloData, EhloParam};
+
+/// A SASL mechanism advertised through the `AUTH` capability.
+///
+/// Unrecognized mechanisms are preserved through `Other` instead of being
+/// dropped, as servers are free to advertise vendor-specific mechanisms.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AuthMechanism {
+    Plain,
+    Login,
+    CramMd5,
+    XOAuth2,
+    Other(String),
+}
+
+impl<'a> From<&'a EhloParam> for AuthMechanism {
+    fn from(param: &'a EhloParam) -> Self {
+        AuthMechanism::from(param.as_str())
+    }
+}
+
+impl<'a> From<&'a str> for AuthMechanism {
+    fn from(s: &'a str) -> Self {
+        match s {
+            "PLAIN" => AuthMechanism::Plain,
+            "LOGIN" => AuthMechanism::Login,
+            "CRAM-MD5" => AuthMechanism::CramMd5,
+            "XOAUTH2" => AuthMechanism::XOAuth2,
+            other => AuthMechanism::Other(other.to_owned()),
+        }
+    }
+}
+
+impl EhloData {
+
+    /// The maximum message size declared through the `SIZE` capability.
+    ///
+    /// Returns `None` if the server didn't advertise `SIZE`, or if it
+    /// advertised `SIZE 0` (no declared limit, per RFC 1870).
+    pub fn max_message_size(&self) -> Option<u64> {
+        let params = self.get_capability_params("SIZE")?;
+        let limit = params.first()?.as_str().parse::<u64>().ok()?;
+        if limit == 0 { None } else { Some(limit) }
+    }
+
+    /// The SASL mechanisms advertised through the `AUTH` capability.
+    pub fn auth_mechanisms(&self) -> Vec<AuthMechanism> {
+        self.get_capability_params("AUTH")
+            .map(|params| params.iter().map(AuthMechanism::from).collect())
+            .unwrap_or_else(Vec::new)
+    }
+
+    /// Whether the server advertised the `PIPELINING` capability.
+    pub fn supports_pipelining(&self) -> bool {
+        self.has_capability("PIPELINING")
+    }
+
+    /// Whether the server advertised `CHUNKING` or `BINARYMIME`.
+    pub fn supports_chunking(&self) -> bool {
+        self.has_capability("CHUNKING") || self.has_capability("BINARYMIME")
+    }
+
+    /// Whether the server advertised the `8BITMIME` capability.
+    pub fn supports_8bitmime(&self) -> bool {
+        self.has_capability("8BITMIME")
+    }
+
+    /// Whether the server advertised the `SMTPUTF8` capability.
+    pub fn supports_smtputf8(&self) -> bool {
+        self.has_capability("SMTPUTF8")
+    }
+
+    /// Whether the server advertised the `STARTTLS` capability.
+    pub fn supports_starttls(&self) -> bool {
+        self.has_capability("STARTTLS")
+    }
+
+    /// Whether the server advertised the `DSN` capability.
+    pub fn supports_dsn(&self) -> bool {
+        self.has_capability("DSN")
+    }
+
+    /// Whether the server advertised the `ENHANCEDSTATUSCODES` capability.
+    pub fn supports_enhanced_status_codes(&self) -> bool {
+        self.has_capability("ENHANCEDSTATUSCODES")
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use ::Response;
+    use ::response::codes::OK;
+    use ::command::ehlo::parse_ehlo_response;
+    use super::AuthMechanism;
+
+    #[test]
+    fn max_message_size_is_parsed() {
+        let response = Response::new(OK, vec![
+            "mail.example.test".to_owned(),
+            "SIZE 36700160".to_owned(),
+        ]);
+        let ehlo_data = parse_ehlo_response(&response).unwrap();
+        assert_eq!(ehlo_data.max_message_size(), Some(36700160));
+    }
+
+    #[test]
+    fn size_zero_means_no_limit() {
+        let response = Response::new(OK, vec![
+            "mail.example.test".to_owned(),
+            "SIZE 0".to_owned(),
+        ]);
+        let ehlo_data = parse_ehlo_response(&response).unwrap();
+        assert_eq!(ehlo_data.max_message_size(), None);
+    }
+
+    #[test]
+    fn auth_mechanisms_are_typed() {
+        let response = Response::new(OK, vec![
+            "mail.example.test".to_owned(),
+            "AUTH PLAIN LOGIN CRAM-MD5 XOAUTH2 FANCY".to_owned(),
+        ]);
+        let ehlo_data = parse_ehlo_response(&response).unwrap();
+        assert_eq!(ehlo_data.auth_mechanisms(), vec![
+            AuthMechanism::Plain,
+            AuthMechanism::Login,
+            AuthMechanism::CramMd5,
+            AuthMechanism::XOAuth2,
+            AuthMechanism::Other("FANCY".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn boolean_capabilities_reflect_presence() {
+        let response = Response::new(OK, vec![
+            "mail.example.test".to_owned(),
+            "PIPELINING".to_owned(),
+            "8BITMIME".to_owned(),
+            "STARTTLS".to_owned(),
+        ]);
+        let ehlo_data = parse_ehlo_response(&response).unwrap();
+        assert!(ehlo_data.supports_pipelining());
+        assert!(ehlo_data.supports_8bitmime());
+        assert!(ehlo_data.supports_starttls());
+        assert!(!ehlo_data.supports_chunking());
+        assert!(!ehlo_data.supports_smtputf8());
+        assert!(!ehlo_data.supports_dsn());
+        assert!(!ehlo_data.supports_enhanced_status_codes());
+    }
+}
@@ -0,0 +1,6 @@
+// Other `data_types` submodules (`Capability`, `EhloParam`, `Domain`, ...)
+// live outside this trimmed checkout; this file only wires in the
+// capability-typing layer added here.
+pub mod capabilities;
+
+pub use self::capabilities::AuthMechanism;
@@ -0,0 +1,15 @@
+extern crate bytes;
+extern crate futures;
+
+pub mod command;
+pub mod data_types;
+pub mod pipelining;
+
+// `Domain`, `ClientId`, `AddressLiteral`, `EhloData`, `EhloParam`,
+// `SyntaxError`, `Cmd`, `ExecFuture`, `Io`, `Response`, the `error` and
+// `response` modules, and their `extern crate` deps live outside this
+// trimmed checkout; this file only wires in what's added here.
+pub use command::ehlo::Ehlo;
+pub use command::greeting::Greeting;
+pub use command::helo::Helo;
+pub use pipelining::PipelinedCmd;
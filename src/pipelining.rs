@@ -0,0 +1,207 @@
+//! Command pipelining (RFC 2920): writing several commands to the wire
+//! back-to-back and flushing once, instead of the one-round-trip-per-command
+//! model `Cmd::exec` implies on its own.
+//!
+//! This only pays off when the server has advertised the `PIPELINING`
+//! capability (`EhloData::supports_pipelining`), and even then not every
+//! command may be pipelined ahead of its predecessor's response (e.g.
+//! `STARTTLS` never can). Gating which batches are safe to build is the
+//! caller's job (don't build a batch that depends on an earlier command's
+//! response); `Io::exec_pipelined` itself only checks that `PIPELINING` was
+//! advertised at all before writing anything.
+use std::io as std_io;
+use std::str::FromStr;
+
+use futures::{Future, Stream};
+use futures::stream;
+
+use ::data_types::Capability;
+use ::error::MissingCapabilities;
+use ::{EhloData, Io, Response};
+
+
+/// A command that can be written onto the wire ahead of reading its
+/// response, as pipelining requires.
+///
+/// `Cmd::exec` couples writing a command with flushing and reading its
+/// response, which only allows one round trip at a time. Implementing
+/// `PipelinedCmd` in addition to `Cmd` lets `Io::exec_pipelined` write a
+/// whole batch up front and read the responses back afterwards, in order.
+pub trait PipelinedCmd {
+    /// Writes this command's wire representation into `io`'s output
+    /// buffer, without flushing.
+    fn write_cmd(&self, io: &mut Io);
+}
+
+/// Checks that the server advertised `PIPELINING`, as required before
+/// calling `Io::exec_pipelined`.
+pub fn check_pipelining_availability(caps: Option<&EhloData>)
+    -> Result<(), MissingCapabilities>
+{
+    match caps {
+        Some(ehlo_data) if ehlo_data.supports_pipelining() => Ok(()),
+        //UNWRAP_SAFE: "PIPELINING" is a valid esmtp-keyword
+        _ => Err(MissingCapabilities::new(vec![Capability::from_str("PIPELINING").unwrap()])),
+    }
+}
+
+/// Anything that can read one SMTP response at a time, in the order they
+/// arrive. Implemented by `Io`; factored out so `read_responses`'s
+/// sequencing can be unit tested without a real connection.
+trait ResponseSource: Sized {
+    fn parse_one_response(self)
+        -> Box<Future<Item = (Self, Result<Response, Response>), Error = std_io::Error>>;
+}
+
+impl ResponseSource for Io {
+    fn parse_one_response(self)
+        -> Box<Future<Item = (Self, Result<Response, Response>), Error = std_io::Error>>
+    {
+        Box::new(Io::parse_response(self))
+    }
+}
+
+/// Reads `count` responses in order, regardless of whether any individual
+/// one failed — a failed command mid-batch doesn't stop the server from
+/// sending a response line for every command that was written.
+fn read_responses<T>(io: T, count: usize)
+    -> Box<Future<Item = (T, Vec<Result<Response, Response>>), Error = std_io::Error>>
+    where T: ResponseSource + 'static
+{
+    let fut = stream::iter_ok::<_, std_io::Error>(0..count)
+        .fold((io, Vec::with_capacity(count)), |(io, mut responses), _| {
+            io.parse_one_response().map(|(io, result)| {
+                responses.push(result);
+                (io, responses)
+            })
+        });
+
+    Box::new(fut)
+}
+
+impl Io {
+
+    /// Writes a batch of commands back-to-back and flushes once, then
+    /// reads one response per command, in the order the commands were
+    /// given.
+    ///
+    /// Fails with `MissingCapabilities` without writing anything if the
+    /// server hasn't advertised `PIPELINING`.
+    pub fn exec_pipelined(mut self, cmds: Vec<Box<PipelinedCmd>>)
+        -> Box<Future<Item = (Io, Vec<Result<Response, Response>>), Error = std_io::Error>>
+    {
+        if let Err(missing) = check_pipelining_availability(self.ehlo_data()) {
+            return Box::new(futures::future::err(
+                std_io::Error::new(std_io::ErrorKind::Other, missing)
+            ));
+        }
+
+        let count = cmds.len();
+        for cmd in &cmds {
+            cmd.write_cmd(&mut self);
+        }
+
+        let fut = self.flush().and_then(move |io| read_responses(io, count));
+
+        Box::new(fut)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io as std_io;
+    use std::collections::VecDeque;
+
+    use futures::Future;
+
+    use ::Response;
+    use ::response::codes::OK;
+    use super::{read_responses, ResponseSource};
+
+    struct MockSource {
+        responses: VecDeque<Result<Response, Response>>,
+    }
+
+    impl ResponseSource for MockSource {
+        fn parse_one_response(mut self)
+            -> Box<Future<Item = (Self, Result<Response, Response>), Error = std_io::Error>>
+        {
+            let next = self.responses.pop_front()
+                .expect("test read more responses than were queued");
+            Box::new(futures::future::ok((self, next)))
+        }
+    }
+
+    fn ok_response(text: &str) -> Response {
+        Response::new(OK, vec![text.to_owned()])
+    }
+
+    #[test]
+    fn reads_n_responses_in_order() {
+        let source = MockSource {
+            responses: vec![
+                Ok(ok_response("mail from ok")),
+                Ok(ok_response("rcpt to ok")),
+                Ok(ok_response("data ok")),
+            ].into(),
+        };
+
+        let (_, responses) = read_responses(source, 3).wait().unwrap();
+
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0].as_ref().unwrap().msg(), &["mail from ok".to_owned()]);
+        assert_eq!(responses[1].as_ref().unwrap().msg(), &["rcpt to ok".to_owned()]);
+        assert_eq!(responses[2].as_ref().unwrap().msg(), &["data ok".to_owned()]);
+    }
+
+    #[test]
+    fn failed_rcpt_mid_batch_does_not_stop_remaining_reads() {
+        let source = MockSource {
+            responses: vec![
+                Ok(ok_response("mail from ok")),
+                Err(ok_response("rcpt to rejected")),
+                Ok(ok_response("data ok")),
+            ].into(),
+        };
+
+        let (_, responses) = read_responses(source, 3).wait().unwrap();
+
+        assert_eq!(responses.len(), 3);
+        assert!(responses[0].is_ok());
+        assert!(responses[1].is_err());
+        assert!(responses[2].is_ok());
+    }
+
+    mod check_pipelining_availability {
+        use ::data_types::EhloParam;
+        use ::{ClientId, EhloData};
+        use std::collections::HashMap;
+        use super::super::check_pipelining_availability;
+
+        #[test]
+        fn rejects_when_ehlo_data_missing() {
+            assert!(check_pipelining_availability(None).is_err());
+        }
+
+        #[test]
+        fn rejects_when_pipelining_not_advertised() {
+            let ehlo_data = EhloData::new(
+                ClientId::Domain("1aim.test".parse().unwrap()),
+                HashMap::new(),
+            );
+            assert!(check_pipelining_availability(Some(&ehlo_data)).is_err());
+        }
+
+        #[test]
+        fn accepts_when_pipelining_advertised() {
+            let mut caps = HashMap::new();
+            caps.insert("PIPELINING".parse().unwrap(), Vec::<EhloParam>::new());
+            let ehlo_data = EhloData::new(
+                ClientId::Domain("1aim.test".parse().unwrap()),
+                caps,
+            );
+            assert!(check_pipelining_availability(Some(&ehlo_data)).is_ok());
+        }
+    }
+}